@@ -0,0 +1,236 @@
+use crate::profile::Profile;
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+type Layer = HashMap<(String, String), String>;
+
+/// Layered configuration store.
+///
+/// Values are resolved in priority order, highest first: explicit overrides
+/// (e.g. CLI flags), environment variables (`RETROVERT_<SECTION>_<KEY>`),
+/// the `retrovert.toml` config file, and finally the loader's built-in
+/// defaults. Use [`Config::get_opt`] for settings that may reasonably be
+/// unset and [`Config::must_get`] for settings the loader cannot run
+/// without.
+pub struct Config {
+    defaults: Layer,
+    file: Layer,
+    env: Layer,
+    overrides: Layer,
+}
+
+impl Config {
+    /// Loads the layered config, reading `retrovert.toml` from the
+    /// profile's config directory if it exists and pulling
+    /// `RETROVERT_<SECTION>_<KEY>` environment variables. A missing file or
+    /// unset env vars are not errors here; only `must_get` enforces that a
+    /// value is ultimately present.
+    pub fn load(profile: &Profile) -> Result<Config> {
+        let mut file = Layer::new();
+
+        let config_path = profile.config_dir().join("retrovert.toml");
+        if config_path.exists() {
+            let contents = std::fs::read_to_string(&config_path)
+                .with_context(|| format!("Unable to read config file \"{:?}\"", config_path))?;
+            file = Self::parse_toml(&contents)
+                .with_context(|| format!("Unable to parse config file \"{:?}\"", config_path))?;
+        }
+
+        Ok(Config {
+            defaults: Self::builtin_defaults(),
+            file,
+            env: Self::read_env(),
+            overrides: Layer::new(),
+        })
+    }
+
+    fn builtin_defaults() -> Layer {
+        let mut defaults = Layer::new();
+        defaults.insert(
+            ("core".to_string(), "path".to_string()),
+            format!(
+                "../retrovert-core/target/debug/librv_core.{}",
+                crate::registry::CORE_EXTENSION
+            ),
+        );
+        defaults.insert(("log".to_string(), "terminal_level".to_string()), "info".to_string());
+        defaults.insert(("log".to_string(), "file_level".to_string()), "trace".to_string());
+        defaults.insert(("log".to_string(), "max_files".to_string()), "5".to_string());
+        defaults
+    }
+
+    fn parse_toml(contents: &str) -> Result<Layer> {
+        let value: toml::Value = contents.parse().context("invalid TOML syntax")?;
+        let table = value
+            .as_table()
+            .context("expected a top-level table of [section] entries")?;
+
+        let mut layer = Layer::new();
+        for (section, entries) in table {
+            let entries = entries
+                .as_table()
+                .with_context(|| format!("expected [{}] to be a table", section))?;
+            for (key, value) in entries {
+                let value = match value {
+                    toml::Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                };
+                layer.insert((section.clone(), key.clone()), value);
+            }
+        }
+        Ok(layer)
+    }
+
+    fn read_env() -> Layer {
+        let mut layer = Layer::new();
+        for (name, value) in std::env::vars_os() {
+            // `vars_os` tolerates non-Unicode entries elsewhere in the
+            // environment; skip a var here only if it, or its own value,
+            // isn't valid Unicode, rather than panicking on the whole
+            // environment the way `std::env::vars()` would.
+            let (Some(name), Some(value)) = (name.to_str(), value.to_str()) else {
+                continue;
+            };
+
+            if let Some(rest) = name.strip_prefix("RETROVERT_") {
+                if let Some((section, key)) = rest.split_once('_') {
+                    layer.insert((section.to_lowercase(), key.to_lowercase()), value.to_string());
+                }
+            }
+        }
+        layer
+    }
+
+    /// Layers an explicit override (e.g. a CLI flag) on top of every other
+    /// source, taking precedence over the config file and environment.
+    pub fn with_override(mut self, section: &str, key: &str, value: impl Into<String>) -> Config {
+        self.overrides
+            .insert((section.to_string(), key.to_string()), value.into());
+        self
+    }
+
+    fn lookup(&self, section: &str, key: &str) -> Option<&str> {
+        let lookup_key = (section.to_string(), key.to_string());
+        self.overrides
+            .get(&lookup_key)
+            .or_else(|| self.env.get(&lookup_key))
+            .or_else(|| self.file.get(&lookup_key))
+            .or_else(|| self.defaults.get(&lookup_key))
+            .map(String::as_str)
+    }
+
+    /// Returns the value for `section.key`, parsed as `T`, or `None` if it
+    /// is unset in every layer or fails to parse.
+    pub fn get_opt<T: FromStr>(&self, section: &str, key: &str) -> Option<T> {
+        self.lookup(section, key).and_then(|v| v.parse().ok())
+    }
+
+    /// Returns the value for `section.key`, parsed as `T`, or a descriptive
+    /// error naming the setting if it is unset in every layer, if the
+    /// highest-priority layer that sets it holds an empty string (lower
+    /// layers are not consulted then), or if it does not parse as `T`.
+    pub fn must_get<T: FromStr>(&self, section: &str, key: &str) -> Result<T> {
+        match self.lookup(section, key) {
+            Some(value) if !value.is_empty() => value.parse().map_err(|_| {
+                anyhow::anyhow!(
+                    "Config value \"{}.{}\" is set to \"{}\", which could not be parsed as the expected type",
+                    section,
+                    key,
+                    value
+                )
+            }),
+            _ => anyhow::bail!(
+                "Required config value \"{}.{}\" is not set. Set it in retrovert.toml, via the RETROVERT_{}_{} environment variable, or pass it explicitly.",
+                section,
+                key,
+                section.to_uppercase(),
+                key.to_uppercase()
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layer(pairs: &[(&str, &str, &str)]) -> Layer {
+        pairs
+            .iter()
+            .map(|(section, key, value)| ((section.to_string(), key.to_string()), value.to_string()))
+            .collect()
+    }
+
+    fn config(defaults: &[(&str, &str, &str)], file: &[(&str, &str, &str)], env: &[(&str, &str, &str)]) -> Config {
+        Config {
+            defaults: layer(defaults),
+            file: layer(file),
+            env: layer(env),
+            overrides: Layer::new(),
+        }
+    }
+
+    #[test]
+    fn falls_back_to_defaults_when_nothing_else_is_set() {
+        let config = config(&[("core", "path", "default")], &[], &[]);
+        assert_eq!(config.get_opt::<String>("core", "path").as_deref(), Some("default"));
+    }
+
+    #[test]
+    fn file_beats_defaults() {
+        let config = config(&[("core", "path", "default")], &[("core", "path", "file")], &[]);
+        assert_eq!(config.get_opt::<String>("core", "path").as_deref(), Some("file"));
+    }
+
+    #[test]
+    fn env_beats_file_and_defaults() {
+        let config = config(
+            &[("core", "path", "default")],
+            &[("core", "path", "file")],
+            &[("core", "path", "env")],
+        );
+        assert_eq!(config.get_opt::<String>("core", "path").as_deref(), Some("env"));
+    }
+
+    #[test]
+    fn override_beats_every_other_layer() {
+        let config = config(
+            &[("core", "path", "default")],
+            &[("core", "path", "file")],
+            &[("core", "path", "env")],
+        )
+        .with_override("core", "path", "override");
+        assert_eq!(config.get_opt::<String>("core", "path").as_deref(), Some("override"));
+    }
+
+    #[test]
+    fn get_opt_is_none_when_unset_in_every_layer() {
+        let config = config(&[], &[], &[]);
+        assert_eq!(config.get_opt::<String>("core", "path"), None);
+    }
+
+    #[test]
+    fn must_get_errors_when_unset_in_every_layer() {
+        let config = config(&[], &[], &[]);
+        assert!(config.must_get::<String>("core", "path").is_err());
+    }
+
+    #[test]
+    fn must_get_errors_when_value_is_empty() {
+        let config = config(&[("core", "path", "")], &[], &[]);
+        assert!(config.must_get::<String>("core", "path").is_err());
+    }
+
+    #[test]
+    fn must_get_errors_when_value_does_not_parse_as_the_requested_type() {
+        let config = config(&[("log", "max_files", "not-a-number")], &[], &[]);
+        assert!(config.must_get::<usize>("log", "max_files").is_err());
+    }
+
+    #[test]
+    fn must_get_succeeds_once_a_layer_sets_the_value() {
+        let config = config(&[], &[], &[("core", "path", "env")]);
+        assert_eq!(config.must_get::<String>("core", "path").unwrap(), "env");
+    }
+}
@@ -0,0 +1,153 @@
+use crate::{Core, CoreCreate, CoreDestroy, CoreLoadUrl, CoreShowArgs, CoreSupportsUrl, CoreUpdate};
+use anyhow::{Context, Result};
+use libloading::Library;
+use std::os::raw::{c_char, c_void};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// The function pointers bound out of a loaded core dylib.
+///
+/// These are plain `fn` pointers rather than `libloading::Symbol`s, so they
+/// outlive the `Library` they came from a copy of — which is what lets
+/// [`CoreHost`] swap the underlying `Library` out from under them.
+struct BoundSymbols {
+    core_create: CoreCreate,
+    core_destroy: CoreDestroy,
+    core_update: CoreUpdate,
+    core_show_args: CoreShowArgs,
+    core_load_url: CoreLoadUrl,
+    core_supports_url: Option<CoreSupportsUrl>,
+}
+
+/// Owns a loaded core dylib and its live `*mut c_void` instance, and can
+/// swap both out at runtime when the dylib on disk changes.
+///
+/// `Core<'a>` borrows its `Library` for the lifetime of its `Symbol`s,
+/// which makes it unsuitable for hot-reload: the `Library` can't be
+/// dropped and replaced while something still borrows from it. `CoreHost`
+/// works around this by copying the bound function pointers out of a
+/// short-lived `Core` (they're `Copy`) and owning the `Library` itself, so
+/// nothing outside this type ever borrows from it.
+pub struct CoreHost {
+    path: PathBuf,
+    // Never read directly, but must stay alive for as long as the bound
+    // function pointers below point into it.
+    #[allow(dead_code)]
+    library: Library,
+    instance: *mut c_void,
+    last_modified: SystemTime,
+    core_create: CoreCreate,
+    core_destroy: CoreDestroy,
+    core_update: CoreUpdate,
+    core_show_args: CoreShowArgs,
+    core_load_url: CoreLoadUrl,
+    core_supports_url: Option<CoreSupportsUrl>,
+}
+
+impl CoreHost {
+    /// Loads the core dylib at `path` and calls `core_create` to bring up
+    /// the initial instance.
+    pub fn new(path: impl Into<PathBuf>) -> Result<CoreHost> {
+        let path = path.into();
+        let (library, bound) = Self::load(&path)?;
+        let instance = (bound.core_create)();
+        let last_modified = Self::modified_time(&path)?;
+
+        Ok(CoreHost {
+            path,
+            library,
+            instance,
+            last_modified,
+            core_create: bound.core_create,
+            core_destroy: bound.core_destroy,
+            core_update: bound.core_update,
+            core_show_args: bound.core_show_args,
+            core_load_url: bound.core_load_url,
+            core_supports_url: bound.core_supports_url,
+        })
+    }
+
+    fn load(path: &Path) -> Result<(Library, BoundSymbols)> {
+        let library = unsafe { Library::new(path) }
+            .with_context(|| format!("Unable to load core dylib from \"{:?}\"", path))?;
+        let core = Core::new(&library)?;
+
+        let bound = BoundSymbols {
+            core_create: *core.core_create_func,
+            core_destroy: *core.core_destroy_func,
+            core_update: *core.core_update_func,
+            core_show_args: *core.core_show_args,
+            core_load_url: *core.core_load_url,
+            core_supports_url: core.core_supports_url.as_deref().copied(),
+        };
+
+        Ok((library, bound))
+    }
+
+    fn modified_time(path: &Path) -> Result<SystemTime> {
+        std::fs::metadata(path)
+            .with_context(|| format!("Unable to stat core dylib \"{:?}\"", path))?
+            .modified()
+            .with_context(|| format!("Platform does not report mtimes for \"{:?}\"", path))
+    }
+
+    /// Checks whether the core dylib on disk has changed since it was
+    /// loaded (or last reloaded) and, if so, swaps it in: destroys the old
+    /// instance, drops the old `Library`, loads the new one, rebinds
+    /// symbols, and creates a fresh instance. Returns whether a reload
+    /// happened.
+    pub fn poll_reload(&mut self) -> Result<bool> {
+        let modified = Self::modified_time(&self.path)?;
+        if modified <= self.last_modified {
+            return Ok(false);
+        }
+
+        // Advance the watermark before attempting the swap so a dylib that
+        // fails to load (e.g. a partial write still in progress) is only
+        // retried on its *next* modification, not on every subsequent poll.
+        self.last_modified = modified;
+
+        let (library, bound) = Self::load(&self.path)?;
+
+        (self.core_destroy)(self.instance, true);
+
+        self.library = library;
+        self.instance = (bound.core_create)();
+        self.core_create = bound.core_create;
+        self.core_destroy = bound.core_destroy;
+        self.core_update = bound.core_update;
+        self.core_show_args = bound.core_show_args;
+        self.core_load_url = bound.core_load_url;
+        self.core_supports_url = bound.core_supports_url;
+
+        Ok(true)
+    }
+
+    pub fn update(&self) -> u64 {
+        (self.core_update)(self.instance)
+    }
+
+    pub fn show_args(&self) {
+        (self.core_show_args)()
+    }
+
+    pub fn load_url(&self, name: *const c_char) {
+        (self.core_load_url)(self.instance, name)
+    }
+
+    /// Whether this core claims support for `name`. Cores that don't
+    /// export `core_supports_url` are treated as not claiming any URL, so
+    /// routing falls through to another registered core.
+    pub fn supports_url(&self, name: *const c_char) -> bool {
+        match self.core_supports_url {
+            Some(core_supports_url) => core_supports_url(name),
+            None => false,
+        }
+    }
+}
+
+impl Drop for CoreHost {
+    fn drop(&mut self) {
+        (self.core_destroy)(self.instance, false);
+    }
+}
@@ -1,11 +1,20 @@
 use anyhow::{bail, Context, Result};
 use std::os::raw::{c_char, c_void};
-use directories::ProjectDirs;
 use libloading::{Library, Symbol};
 use log::{LevelFilter, Log, SetLoggerError};
 use simplelog::*;
 use std::fs::File;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+mod config;
+mod host;
+mod profile;
+mod registry;
+
+pub use config::Config;
+pub use host::CoreHost;
+pub use profile::Profile;
+pub use registry::{discover_cores, CoreDescriptor, CoreRegistry};
 
 #[allow(dead_code)]
 pub type SetupLogger =
@@ -26,64 +35,142 @@ pub type CoreShowArgs = fn();
 #[allow(dead_code)]
 pub type CoreLoadUrl = fn(core: *mut c_void, name: *const c_char);
 
+#[allow(dead_code)]
+pub type CoreAbiVersion = fn() -> u32;
+
+#[allow(dead_code)]
+pub type CoreSupportsUrl = fn(name: *const c_char) -> bool;
+
+/// The ABI version this loader was built against. Bump whenever the
+/// `core_*` function signatures or calling convention change in a way
+/// that would make an old core dylib unsafe to call.
+pub const CORE_ABI_VERSION: u32 = 1;
+
 pub struct Core<'a> {
     pub core_create_func: Symbol<'a, CoreCreate>,
     pub core_destroy_func: Symbol<'a, CoreDestroy>,
     pub core_update_func: Symbol<'a, CoreUpdate>,
     pub core_show_args: Symbol<'a, CoreShowArgs>,
     pub core_load_url: Symbol<'a, CoreLoadUrl>,
+    /// Present only on cores that can tell the registry whether they
+    /// support a given URL/file type; `None` on cores that predate it.
+    pub core_supports_url: Option<Symbol<'a, CoreSupportsUrl>>,
 }
 
 impl<'a> Core<'a> {
-    pub fn init_logging() -> Result<()> {
-        let dirs = match ProjectDirs::from("app", "tbl", "retrovert") {
-            Some(dirs) => dirs,
-            None => bail!("Unable to get a user directory for config and log output. Please report this problem with a description of your system."),
-        };
-
-        std::fs::create_dir_all(dirs.config_dir()).with_context(|| {
-            format!("Unable to create the directory \"{:?}\" Make sure the application are allowed to write here. If you think this location is bad please report it.",
-                dirs.config_dir())
-        })?;
-
-        std::fs::create_dir_all(dirs.config_dir())
-            .with_context(|| "unable to create all needed directories".to_string())?;
-
-        let log_file_path = Path::new(dirs.config_dir()).join("retrovert.log");
+    pub fn init_logging(profile: &Profile, config: &Config) -> Result<()> {
+        let max_files: usize = config.get_opt("log", "max_files").unwrap_or(5);
+        let log_file_path = Self::rotate_logs(profile.log_dir(), max_files)?;
 
         let log_file = File::create(&log_file_path).with_context(|| {
             format!("Unable to create file \"{:?}\" Make sure the application has access to this location or report this problem if you think the location is bad",
                 log_file_path)
         })?;
 
+        let terminal_level: String = config.must_get("log", "terminal_level")?;
+        let file_level: String = config.must_get("log", "file_level")?;
+
+        let mut log_config = ConfigBuilder::new();
+        log_config.set_time_format_rfc3339();
+        log_config.set_thread_level(LevelFilter::Debug);
+        log_config.set_target_level(LevelFilter::Debug);
+        let log_config = log_config.build();
+
         CombinedLogger::init(vec![
             TermLogger::new(
-                //LevelFilter::Trace,
-                LevelFilter::Info,
-                Config::default(),
+                Self::parse_level(&terminal_level)?,
+                log_config.clone(),
                 TerminalMode::Mixed,
                 ColorChoice::Auto,
             ),
-            WriteLogger::new(LevelFilter::Trace, Config::default(), log_file),
+            WriteLogger::new(Self::parse_level(&file_level)?, log_config, log_file),
         ])?;
 
         Ok(())
     }
 
-    pub fn load_core(core_filename: &Option<String>) -> Result<Library> {
-        let filename = if let Some(core_filename) = core_filename {
-            core_filename
+    /// Shifts any existing `retrovert.log[.N]` files in `log_dir` up by one
+    /// generation (dropping the oldest once there are `max_files` of them)
+    /// and returns the now-vacant path for the new log file. `max_files ==
+    /// 0` disables rotation and always truncates in place.
+    fn rotate_logs(log_dir: &Path, max_files: usize) -> Result<PathBuf> {
+        let current = log_dir.join("retrovert.log");
+
+        if max_files == 0 {
+            return Ok(current);
+        }
+
+        let oldest = log_dir.join(format!("retrovert.log.{}", max_files));
+        if oldest.exists() {
+            std::fs::remove_file(&oldest)
+                .with_context(|| format!("Unable to remove oldest log file \"{:?}\"", oldest))?;
+        }
+
+        for generation in (1..max_files).rev() {
+            let from = log_dir.join(format!("retrovert.log.{}", generation));
+            if from.exists() {
+                let to = log_dir.join(format!("retrovert.log.{}", generation + 1));
+                std::fs::rename(&from, &to)
+                    .with_context(|| format!("Unable to rotate \"{:?}\" to \"{:?}\"", from, to))?;
+            }
+        }
+
+        if current.exists() {
+            let to = log_dir.join("retrovert.log.1");
+            std::fs::rename(&current, &to)
+                .with_context(|| format!("Unable to rotate \"{:?}\" to \"{:?}\"", current, to))?;
+        }
+
+        Ok(current)
+    }
+
+    fn parse_level(level: &str) -> Result<LevelFilter> {
+        level
+            .parse()
+            .with_context(|| format!("\"{}\" is not a valid log level", level))
+    }
+
+    pub fn load_core(profile: &Profile, config: &Config) -> Result<Library> {
+        let configured_path: String = config.must_get("core", "path")?;
+        let configured_path = Path::new(&configured_path);
+
+        let path = if configured_path.exists() {
+            configured_path.to_path_buf()
         } else {
-            //"../retrovert-core/target/debug/librv_core.so"
-            "../retrovert-core/target/debug/librv_core.dylib"
+            profile.base_dir().join(configured_path)
         };
 
-        let lib = unsafe { Library::new(filename)? };
+        let lib = unsafe { Library::new(&path) }.with_context(|| {
+            format!(
+                "Unable to load core dylib from \"{:?}\" (also checked \"{:?}\")",
+                configured_path, path
+            )
+        })?;
         Ok(lib)
     }
 
     pub fn new(lib: &'a Library) -> Result<Core<'a>> {
         unsafe {
+            match lib.get::<CoreAbiVersion>(b"core_abi_version\0") {
+                Ok(core_abi_version) => {
+                    let core_version = core_abi_version();
+                    if core_version != CORE_ABI_VERSION {
+                        bail!(
+                            "Core ABI mismatch: this loader expects ABI version {}, but the core reports version {}. \
+                             Rebuild the core against the current loader, or install a loader matching the core's ABI.",
+                            CORE_ABI_VERSION,
+                            core_version
+                        );
+                    }
+                }
+                Err(_) => {
+                    log::warn!(
+                        "Core does not export \"core_abi_version\"; assuming ABI version 0. \
+                         This core predates the ABI handshake and may be incompatible with this loader."
+                    );
+                }
+            }
+
             let ret = lib.get::<SetupLogger>(b"core_setup_logger");
             if let Ok(setup_logger) = ret {
                 setup_logger(log::logger(), log::max_level()).unwrap();
@@ -104,6 +191,8 @@ impl<'a> Core<'a> {
             let core_load_url: Symbol<CoreLoadUrl> = lib
                 .get(b"core_load_url\0")
                 .context("Unable to find \"core_load_url\" function")?;
+            let core_supports_url: Option<Symbol<CoreSupportsUrl>> =
+                lib.get(b"core_supports_url\0").ok();
 
             Ok(Core {
                 core_create_func,
@@ -111,7 +200,85 @@ impl<'a> Core<'a> {
                 core_update_func,
                 core_show_args,
                 core_load_url,
+                core_supports_url,
             })
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_log_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "retrovert-core-loader-test-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn rotate_logs_leaves_file_in_place_when_rotation_disabled() {
+        let dir = temp_log_dir("disabled");
+        std::fs::write(dir.join("retrovert.log"), "current").unwrap();
+
+        let path = Core::rotate_logs(&dir, 0).unwrap();
+
+        assert_eq!(path, dir.join("retrovert.log"));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "current");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn rotate_logs_shifts_current_into_generation_one() {
+        let dir = temp_log_dir("shift-one");
+        std::fs::write(dir.join("retrovert.log"), "current").unwrap();
+
+        let path = Core::rotate_logs(&dir, 1).unwrap();
+
+        assert_eq!(path, dir.join("retrovert.log"));
+        assert!(!dir.join("retrovert.log").exists());
+        assert_eq!(
+            std::fs::read_to_string(dir.join("retrovert.log.1")).unwrap(),
+            "current"
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn rotate_logs_shifts_every_generation_and_drops_the_oldest() {
+        let dir = temp_log_dir("shift-chain");
+        std::fs::write(dir.join("retrovert.log"), "current").unwrap();
+        std::fs::write(dir.join("retrovert.log.1"), "gen1").unwrap();
+        std::fs::write(dir.join("retrovert.log.2"), "gen2-should-be-dropped").unwrap();
+
+        let path = Core::rotate_logs(&dir, 2).unwrap();
+
+        assert_eq!(path, dir.join("retrovert.log"));
+        assert!(!dir.join("retrovert.log").exists());
+        assert_eq!(
+            std::fs::read_to_string(dir.join("retrovert.log.1")).unwrap(),
+            "current"
+        );
+        assert_eq!(
+            std::fs::read_to_string(dir.join("retrovert.log.2")).unwrap(),
+            "gen1"
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn rotate_logs_is_a_no_op_when_nothing_exists_yet() {
+        let dir = temp_log_dir("first-run");
+
+        let path = Core::rotate_logs(&dir, 5).unwrap();
+
+        assert_eq!(path, dir.join("retrovert.log"));
+        assert!(!path.exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,165 @@
+use anyhow::{Context, Result};
+use directories::ProjectDirs;
+use std::path::{Path, PathBuf};
+
+const ENV_DIR_OVERRIDE: &str = "RETROVERT_DIR";
+
+/// Resolved filesystem locations for a single retrovert install or test
+/// sandbox.
+///
+/// By default these live under the platform's data directory (e.g.
+/// `~/.local/share/retrovert` on Linux), but the whole tree can be
+/// relocated with the `RETROVERT_DIR` environment variable, and a named
+/// profile can be layered underneath it so that parallel installs or test
+/// runs don't clobber each other's state.
+pub struct Profile {
+    base_dir: PathBuf,
+    config_dir: PathBuf,
+    cache_dir: PathBuf,
+    log_dir: PathBuf,
+}
+
+impl Profile {
+    /// Resolves a profile's directories, creating them if necessary.
+    ///
+    /// `name`, if given, selects a named subdirectory of the base data
+    /// directory, e.g. `Profile::load(Some("test"))` keeps a test sandbox
+    /// isolated from the default profile.
+    pub fn load(name: Option<&str>) -> Result<Profile> {
+        let mut base_dir = match std::env::var_os(ENV_DIR_OVERRIDE) {
+            Some(dir) => PathBuf::from(dir),
+            None => {
+                let dirs = ProjectDirs::from("app", "tbl", "retrovert").with_context(|| {
+                    "Unable to get a user directory for config and log output. Please report this problem with a description of your system."
+                })?;
+                dirs.data_dir().to_path_buf()
+            }
+        };
+
+        if let Some(name) = name {
+            base_dir.push(name);
+        }
+
+        let base_dir = Self::ensure_dir(&base_dir)?;
+        let config_dir = Self::ensure_dir(&base_dir.join("config"))?;
+        let cache_dir = Self::ensure_dir(&base_dir.join("cache"))?;
+        let log_dir = Self::ensure_dir(&base_dir.join("logs"))?;
+
+        Ok(Profile {
+            base_dir,
+            config_dir,
+            cache_dir,
+            log_dir,
+        })
+    }
+
+    /// Creates `path` (and any parents) if it doesn't exist, then
+    /// canonicalizes it so every consumer sees the same symlink-resolved
+    /// path regardless of how it was originally specified.
+    fn ensure_dir(path: &Path) -> Result<PathBuf> {
+        std::fs::create_dir_all(path).with_context(|| {
+            format!(
+                "Unable to create the directory \"{:?}\". Make sure the application is allowed to write here. If you think this location is bad please report it.",
+                path
+            )
+        })?;
+        path.canonicalize()
+            .with_context(|| format!("Unable to canonicalize \"{:?}\"", path))
+    }
+
+    pub fn base_dir(&self) -> &Path {
+        &self.base_dir
+    }
+
+    pub fn config_dir(&self) -> &Path {
+        &self.config_dir
+    }
+
+    pub fn cache_dir(&self) -> &Path {
+        &self.cache_dir
+    }
+
+    pub fn log_dir(&self) -> &Path {
+        &self.log_dir
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    // `RETROVERT_DIR` is process-global state; serialize the tests below so
+    // they don't race on whichever value the env var currently holds.
+    static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "retrovert-core-loader-profile-test-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn load_with_dir_override(dir: &Path, name: Option<&str>) -> Result<Profile> {
+        // SAFETY: callers hold `ENV_LOCK` for the duration of the override,
+        // so no other test thread observes or mutates the env var at the
+        // same time.
+        unsafe {
+            std::env::set_var(ENV_DIR_OVERRIDE, dir);
+        }
+        let result = Profile::load(name);
+        unsafe {
+            std::env::remove_var(ENV_DIR_OVERRIDE);
+        }
+        result
+    }
+
+    #[test]
+    fn load_honors_retrovert_dir_override() {
+        let _guard = ENV_LOCK.lock().unwrap();
+        let base = temp_dir("override");
+
+        let profile = load_with_dir_override(&base, None).unwrap();
+
+        assert_eq!(profile.base_dir(), base.canonicalize().unwrap());
+        let _ = std::fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn load_nests_a_named_profile_under_the_base_dir() {
+        let _guard = ENV_LOCK.lock().unwrap();
+        let base = temp_dir("named");
+
+        let profile = load_with_dir_override(&base, Some("test")).unwrap();
+
+        assert_eq!(profile.base_dir(), base.join("test").canonicalize().unwrap());
+        let _ = std::fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn load_creates_and_canonicalizes_every_directory() {
+        let _guard = ENV_LOCK.lock().unwrap();
+        let base = temp_dir("dirs");
+
+        let profile = load_with_dir_override(&base, None).unwrap();
+
+        for dir in [
+            profile.base_dir(),
+            profile.config_dir(),
+            profile.cache_dir(),
+            profile.log_dir(),
+        ] {
+            assert!(dir.is_dir());
+            assert_eq!(dir, dir.canonicalize().unwrap());
+        }
+
+        assert_eq!(profile.config_dir(), profile.base_dir().join("config"));
+        assert_eq!(profile.cache_dir(), profile.base_dir().join("cache"));
+        assert_eq!(profile.log_dir(), profile.base_dir().join("logs"));
+
+        let _ = std::fs::remove_dir_all(&base);
+    }
+}
@@ -0,0 +1,140 @@
+use crate::{Config, CoreHost, Profile};
+use anyhow::{bail, Context, Result};
+use std::os::raw::c_char;
+use std::path::{Path, PathBuf};
+
+/// The core dylib extension for the platform this loader was built for,
+/// e.g. to build a default `core.path` or to filter `discover_cores`
+/// candidates.
+#[cfg(target_os = "macos")]
+pub(crate) const CORE_EXTENSION: &str = "dylib";
+#[cfg(target_os = "windows")]
+pub(crate) const CORE_EXTENSION: &str = "dll";
+#[cfg(all(unix, not(target_os = "macos")))]
+pub(crate) const CORE_EXTENSION: &str = "so";
+
+/// A core dylib found on disk, not yet loaded.
+#[derive(Debug, Clone)]
+pub struct CoreDescriptor {
+    pub path: PathBuf,
+    pub name: String,
+}
+
+/// Enumerates every core dylib candidate: the profile's `cores/` directory
+/// plus any directories listed in `core.search_paths` (comma separated),
+/// picking the platform-appropriate extension (`.so` / `.dylib` / `.dll`)
+/// automatically.
+pub fn discover_cores(profile: &Profile, config: &Config) -> Result<Vec<CoreDescriptor>> {
+    let mut search_dirs = vec![profile.base_dir().join("cores")];
+
+    if let Some(search_paths) = config.get_opt::<String>("core", "search_paths") {
+        search_dirs.extend(
+            search_paths
+                .split(',')
+                .map(str::trim)
+                .filter(|p| !p.is_empty())
+                .map(PathBuf::from),
+        );
+    }
+
+    let mut cores = Vec::new();
+    for dir in &search_dirs {
+        scan_dir(dir, &mut cores)?;
+    }
+    Ok(cores)
+}
+
+fn scan_dir(dir: &Path, cores: &mut Vec<CoreDescriptor>) -> Result<()> {
+    if !dir.is_dir() {
+        return Ok(());
+    }
+
+    for entry in std::fs::read_dir(dir)
+        .with_context(|| format!("Unable to read core directory \"{:?}\"", dir))?
+    {
+        let path = entry
+            .with_context(|| format!("Unable to read an entry of \"{:?}\"", dir))?
+            .path();
+
+        if path.extension().and_then(|ext| ext.to_str()) != Some(CORE_EXTENSION) {
+            continue;
+        }
+
+        let name = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .map(str::to_string)
+            .unwrap_or_else(|| path.to_string_lossy().into_owned());
+
+        cores.push(CoreDescriptor { path, name });
+    }
+
+    Ok(())
+}
+
+/// Hosts several cores at once and routes `core_load_url` calls to
+/// whichever registered core claims support for the given URL/file type.
+pub struct CoreRegistry {
+    hosts: Vec<(String, CoreHost)>,
+}
+
+impl CoreRegistry {
+    /// Loads every descriptor into a [`CoreHost`]. A core that fails to
+    /// load is logged and skipped rather than failing the whole registry,
+    /// so one broken plugin doesn't take the others down with it.
+    pub fn load(descriptors: &[CoreDescriptor]) -> Result<CoreRegistry> {
+        let mut hosts = Vec::new();
+
+        for descriptor in descriptors {
+            match CoreHost::new(&descriptor.path) {
+                Ok(host) => hosts.push((descriptor.name.clone(), host)),
+                Err(err) => log::warn!(
+                    "Skipping core \"{}\" ({:?}): {:#}",
+                    descriptor.name,
+                    descriptor.path,
+                    err
+                ),
+            }
+        }
+
+        if hosts.is_empty() {
+            bail!("No core dylibs could be loaded from the discovered candidates");
+        }
+
+        Ok(CoreRegistry { hosts })
+    }
+
+    /// Routes `name` to the first registered core that claims support for
+    /// it, in registration order.
+    pub fn load_url(&self, name: *const c_char) -> Result<()> {
+        for (_, host) in &self.hosts {
+            if host.supports_url(name) {
+                host.load_url(name);
+                return Ok(());
+            }
+        }
+        bail!("No registered core claims support for this URL/file type")
+    }
+
+    /// Polls every registered core for a reload, returning whether any of
+    /// them swapped in a new dylib. A core that fails to reload is logged
+    /// and skipped for this tick rather than aborting the poll, so one
+    /// broken core doesn't block reload of the others.
+    pub fn poll_reload(&mut self) -> Result<bool> {
+        let mut reloaded = false;
+        for (name, host) in &mut self.hosts {
+            match host.poll_reload() {
+                Ok(did_reload) => reloaded |= did_reload,
+                Err(err) => log::warn!("Core \"{}\" failed to reload: {:#}", name, err),
+            }
+        }
+        Ok(reloaded)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&CoreHost> {
+        self.hosts
+            .iter()
+            .find(|(host_name, _)| host_name == name)
+            .map(|(_, host)| host)
+    }
+}